@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: MIT
+
+// Runs the local OpenAI-compatible server (see `server`) so editors and
+// other OpenAI clients can point at `127.0.0.1:8000` to drive
+// `gpt_auto_test`'s codegen interactively. Lives in `gpt-macro-core`
+// rather than the `gpt-macro` proc-macro crate: a `proc-macro = true`
+// crate can only export `#[proc_macro_attribute]`-style items, so it
+// can't also be a regular library a `[[bin]]` depends on.
+
+use gpt_macro_core::server::serve;
+use tokio::sync::oneshot;
+
+#[tokio::main]
+async fn main() {
+    let addr = ([127, 0, 0, 1], 8000).into();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        tokio::signal::ctrl_c().await.ok();
+        let _ = shutdown_tx.send(());
+    });
+
+    println!("gpt-macro-serve listening on http://{}", addr);
+    if let Err(err) = serve(addr, shutdown_rx).await {
+        eprintln!("gpt-macro-serve error: {}", err);
+    }
+}
@@ -0,0 +1,469 @@
+// SPDX-License-Identifier: MIT
+// Akira Moroo <retrage01@gmail.com> 2023
+
+use hyper::client::HttpConnector;
+use hyper::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use hyper::{Body, Client, Request, Uri};
+use hyper_proxy::{Intercept, Proxy, ProxyConnector};
+use hyper_tls::HttpsConnector;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use tokio::runtime::Runtime;
+
+use std::ops::ControlFlow;
+
+use crate::completion::CodeCompletion;
+use crate::config::ClientExtra;
+use crate::retry;
+
+#[derive(Clone, Deserialize, Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+enum Role {
+    User,
+    System,
+    Assistant,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug)]
+struct ChatMessage {
+    role: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<ToolCall>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct ToolCall {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    function: FunctionCall,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct FunctionCall {
+    name: String,
+    arguments: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Tool {
+    #[serde(rename = "type")]
+    kind: String,
+    function: ToolFunction,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ToolFunction {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+/// The arguments of the forced `emit_tests` tool call, i.e. the extracted
+/// test code with no surrounding prose.
+#[derive(Debug, Deserialize)]
+struct EmitTestsArgs {
+    tests: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct Chat {
+    model: String,
+    messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<Tool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ChatCompletion {
+    id: String,
+    object: String,
+    created: u64,
+    choices: Vec<ChatChoice>,
+    usage: ChatUsage,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ChatChoice {
+    index: u32,
+    message: ChatMessage,
+    finish_reason: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ChatUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    total_tokens: u32,
+}
+
+/// One `data: {...}` frame of a `text/event-stream` response.
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    choices: Vec<ChatChunkChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChunkChoice {
+    delta: ChatDelta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChatDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+pub struct ChatGPT {
+    chat: Chat,
+    extra: Option<ClientExtra>,
+}
+
+impl ChatGPT {
+    const URL: &'static str = "https://api.openai.com/v1/chat/completions";
+    const MODEL: &'static str = "gpt-3.5-turbo";
+    const EMIT_TESTS_FN: &'static str = "emit_tests";
+
+    fn add_message(&mut self, role: Role, content: String) {
+        self.chat.messages.push(ChatMessage {
+            role,
+            content: Some(content),
+            tool_calls: None,
+        });
+    }
+
+    /// Reads a `text/event-stream` body frame by frame, printing each delta
+    /// as it arrives and accumulating them into the full message content.
+    ///
+    /// A frame's `choices` array can be empty (e.g. Azure OpenAI's leading
+    /// content-filter annotation frame, or OpenAI's trailing usage frame
+    /// when `stream_options.include_usage` is set), so it's read with
+    /// `.first()` rather than indexed.
+    async fn consume_stream(body: Body) -> Result<String, Box<dyn std::error::Error>> {
+        let mut content = String::new();
+        let mut parse_err = None;
+        retry::for_each_sse_data(body, |data| {
+            let chunk: ChatCompletionChunk = match serde_json::from_str(data) {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    parse_err = Some(err);
+                    return ControlFlow::Break(());
+                }
+            };
+            if let Some(delta) = chunk
+                .choices
+                .first()
+                .and_then(|choice| choice.delta.content.clone())
+            {
+                print!("{}", delta);
+                use std::io::Write;
+                std::io::stdout().flush().ok();
+                content.push_str(&delta);
+            }
+            ControlFlow::Continue(())
+        })
+        .await?;
+        if let Some(err) = parse_err {
+            return Err(err.into());
+        }
+        Ok(content)
+    }
+
+    async fn completion(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let api_key = self
+            .extra
+            .as_ref()
+            .and_then(|e| e.api_key.clone())
+            .unwrap_or_else(|| {
+                std::env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY is not set")
+            });
+        let url = self
+            .extra
+            .as_ref()
+            .and_then(|e| e.api_base.clone())
+            .unwrap_or_else(|| Self::URL.to_string());
+        let uri: Uri = url.parse()?;
+        let https_connector = HttpsConnector::new();
+
+        let proxy_uri = self
+            .extra
+            .as_ref()
+            .and_then(|e| e.proxy.clone())
+            .or_else(|| std::env::var("HTTP_PROXY").ok());
+        let proxy_connector = if let Some(proxy_uri) = proxy_uri {
+            let proxy_uri = proxy_uri.parse().unwrap();
+            let proxy = Proxy::new(Intercept::All, proxy_uri);
+            let proxy_connector =
+                ProxyConnector::from_proxy(https_connector.clone(), proxy).unwrap();
+            Some(proxy_connector)
+        } else {
+            None
+        };
+        let client = proxy_connector.map_or_else(
+            || Box::new(Client::builder().build::<_, hyper::Body>(https_connector)) as Box<dyn Any>,
+            |proxy| Box::new(Client::builder().build::<_, hyper::Body>(proxy)),
+        );
+
+        let request = move |req: Request<Body>| {
+            if let Some(c) = client.downcast_ref::<Client<HttpsConnector<HttpConnector>>>() {
+                c.request(req)
+            } else if let Some(c) =
+                client.downcast_ref::<Client<ProxyConnector<HttpsConnector<HttpConnector>>>>()
+            {
+                c.request(req)
+            } else {
+                panic!("Unknown client type");
+            }
+        };
+        let connect_timeout = self.extra.as_ref().and_then(|e| e.connect_timeout);
+        let max_retries = self
+            .extra
+            .as_ref()
+            .and_then(|e| e.max_retries)
+            .unwrap_or(retry::DEFAULT_MAX_RETRIES);
+        let base_delay_ms = self
+            .extra
+            .as_ref()
+            .and_then(|e| e.retry_base_delay_ms)
+            .unwrap_or(retry::DEFAULT_RETRY_BASE_DELAY_MS);
+
+        let mut attempt: u32 = 0;
+        let response = loop {
+            let body = Body::from(serde_json::to_string(&self.chat)?);
+            let mut request_body = Request::new(body);
+
+            *request_body.method_mut() = hyper::Method::POST;
+            *request_body.uri_mut() = uri.clone();
+
+            request_body
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+            request_body.headers_mut().insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {}", api_key)).unwrap(),
+            );
+            if let Some(organization_id) =
+                self.extra.as_ref().and_then(|e| e.organization_id.clone())
+            {
+                request_body.headers_mut().insert(
+                    "OpenAI-Organization",
+                    HeaderValue::from_str(&organization_id).unwrap(),
+                );
+            }
+
+            let response = match connect_timeout {
+                Some(secs) => {
+                    tokio::time::timeout(
+                        std::time::Duration::from_secs(secs),
+                        request(request_body),
+                    )
+                    .await??
+                }
+                None => request(request_body).await?,
+            };
+
+            if response.status().is_success() {
+                break response;
+            }
+            if !retry::should_retry(response.status(), attempt, max_retries) {
+                return Err(retry::api_error("ChatGPT", response).await);
+            }
+
+            tokio::time::sleep(retry::retry_delay(&response, attempt, base_delay_ms)).await;
+            attempt += 1;
+        };
+        if self.chat.stream {
+            let content = Self::consume_stream(response.into_body()).await?;
+            println!();
+            self.add_message(Role::Assistant, content);
+            return Ok(());
+        }
+
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await?;
+        let body_str = String::from_utf8(body_bytes.to_vec())?;
+
+        let chat_completion: ChatCompletion = serde_json::from_str(&body_str)?;
+        let message = chat_completion.choices[0].message.clone();
+
+        if let Some(content) = &message.content {
+            println!("Response from ChatGPT:\n{}", content);
+        }
+
+        self.chat.messages.push(message);
+
+        Ok(())
+    }
+
+    /// Enables `stream: true` requests, printing deltas as they arrive
+    /// instead of waiting for the full response body.
+    pub fn set_stream(&mut self, stream: bool) {
+        self.chat.stream = stream;
+    }
+
+    /// Extracts the test code from the forced `emit_tests` tool call's
+    /// `tests` argument. `ChatGPT::new()` always sets `tool_choice` to
+    /// `emit_tests`, so the API is guaranteed to reply with that tool call
+    /// rather than free-text prose to scrape.
+    fn extract_code(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let last_message = &self.chat.messages[self.chat.messages.len() - 1];
+
+        let tool_call = last_message
+            .tool_calls
+            .as_ref()
+            .and_then(|calls| calls.first())
+            .ok_or("No tool call in response")?;
+        let args: EmitTestsArgs = serde_json::from_str(&tool_call.function.arguments)?;
+        Ok(args.tests.trim().to_string())
+    }
+}
+
+impl CodeCompletion for ChatGPT {
+    fn new() -> Self {
+        let tools = vec![Tool {
+            kind: "function".to_string(),
+            function: ToolFunction {
+                name: Self::EMIT_TESTS_FN.to_string(),
+                description: "Emit the generated Rust test functions.".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tests": {
+                            "type": "string",
+                            "description": "The generated test code, as it should appear in the source file.",
+                        },
+                    },
+                    "required": ["tests"],
+                }),
+            },
+        }];
+        Self {
+            chat: Chat {
+                model: Self::MODEL.to_string(),
+                messages: vec![],
+                stream: false,
+                tools: Some(tools),
+                tool_choice: Some(serde_json::json!({
+                    "type": "function",
+                    "function": { "name": Self::EMIT_TESTS_FN },
+                })),
+                temperature: None,
+                max_tokens: None,
+            },
+            extra: None,
+        }
+    }
+
+    fn configure(&mut self, extra: &ClientExtra) {
+        if let Some(stream) = extra.stream {
+            self.set_stream(stream);
+        }
+        self.extra = Some(extra.clone());
+    }
+
+    fn set_model(&mut self, model: String) {
+        self.chat.model = model;
+    }
+
+    fn set_temperature(&mut self, temperature: f32) {
+        self.chat.temperature = Some(temperature);
+    }
+
+    fn set_max_tokens(&mut self, max_tokens: u32) {
+        self.chat.max_tokens = Some(max_tokens);
+    }
+
+    fn init(&mut self, init_prompt: String) {
+        self.add_message(Role::System, init_prompt);
+    }
+
+    fn add_context(&mut self, context: String) {
+        self.add_message(Role::User, context);
+    }
+
+    fn code_completion(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+        let rt = Runtime::new()?;
+
+        rt.block_on(self.completion())?;
+
+        self.extract_code()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::body::Bytes;
+
+    #[tokio::test]
+    async fn consume_stream_skips_frames_with_empty_choices() {
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            // A leading content-filter annotation frame (as Azure OpenAI
+            // sends) and a trailing usage frame (as OpenAI sends with
+            // `stream_options.include_usage`) both carry an empty
+            // `choices` array.
+            sender
+                .send_data(Bytes::from("data: {\"choices\":[]}\n"))
+                .await
+                .ok();
+            sender
+                .send_data(Bytes::from(
+                    "data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n",
+                ))
+                .await
+                .ok();
+            sender
+                .send_data(Bytes::from("data: {\"choices\":[]}\n"))
+                .await
+                .ok();
+            sender.send_data(Bytes::from("data: [DONE]\n")).await.ok();
+        });
+
+        let content = ChatGPT::consume_stream(body).await.unwrap();
+        assert_eq!(content, "hi");
+    }
+
+    #[test]
+    fn extract_code_reads_the_emit_tests_tool_call() {
+        let mut chatgpt = ChatGPT::new();
+        chatgpt.chat.messages.push(ChatMessage {
+            role: Role::Assistant,
+            content: None,
+            tool_calls: Some(vec![ToolCall {
+                id: "call_0".to_string(),
+                kind: "function".to_string(),
+                function: FunctionCall {
+                    name: ChatGPT::EMIT_TESTS_FN.to_string(),
+                    arguments: serde_json::json!({ "tests": "#[test]\nfn it_works() {}" })
+                        .to_string(),
+                },
+            }]),
+        });
+
+        let code = chatgpt.extract_code().unwrap();
+        assert_eq!(code, "#[test]\nfn it_works() {}");
+    }
+
+    #[test]
+    fn extract_code_errors_without_a_tool_call() {
+        let mut chatgpt = ChatGPT::new();
+        chatgpt.chat.messages.push(ChatMessage {
+            role: Role::Assistant,
+            content: Some("no tool call here".to_string()),
+            tool_calls: None,
+        });
+
+        assert!(chatgpt.extract_code().is_err());
+    }
+}
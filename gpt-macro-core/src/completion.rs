@@ -0,0 +1,33 @@
+// SPDX-License-Identifier: MIT
+
+use crate::config::ClientExtra;
+
+/// Common interface implemented by every completion backend (`ChatGPT`,
+/// `TextCompletion`, and any provider registered via `register_client!`).
+pub trait CodeCompletion {
+    fn new() -> Self
+    where
+        Self: Sized;
+
+    /// Applies the provider-specific overrides (`api_base`, `api_key`,
+    /// `organization_id`, `proxy`, `connect_timeout`) carried in a client's
+    /// `extra` config block. Backends that don't support an override are
+    /// free to ignore it.
+    fn configure(&mut self, _extra: &ClientExtra) {}
+
+    /// Overrides the model used for generation, e.g. from a
+    /// `#[gpt_auto_test(model = "gpt-4o")]` parameter.
+    fn set_model(&mut self, _model: String) {}
+
+    /// Overrides the sampling temperature.
+    fn set_temperature(&mut self, _temperature: f32) {}
+
+    /// Overrides the maximum number of tokens to generate.
+    fn set_max_tokens(&mut self, _max_tokens: u32) {}
+
+    fn init(&mut self, init_prompt: String);
+
+    fn add_context(&mut self, context: String);
+
+    fn code_completion(&mut self) -> Result<String, Box<dyn std::error::Error>>;
+}
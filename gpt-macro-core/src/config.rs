@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: MIT
+
+// Provider registry: declares the set of pluggable completion backends and
+// the config file format used to pick one at runtime, instead of hardcoding
+// `ChatGPT`/`TextCompletion` as the only two `CodeCompletion` impls.
+
+use serde::Deserialize;
+
+use crate::chatgpt::ChatGPT;
+use crate::completion::CodeCompletion;
+use crate::text_completion::TextCompletion;
+
+/// Where `ClientConfig::init_or_default` looks for a provider config by
+/// default, relative to the current working directory of the process.
+pub const DEFAULT_CONFIG_PATH: &str = "gpt-macro.toml";
+
+/// Overrides common to every backend, read from a client's `extra` block.
+///
+/// Lets a config file target Azure OpenAI, a local llama.cpp/ollama
+/// OpenAI-compatible server, or a self-hosted proxy without editing the
+/// crate.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ClientExtra {
+    #[serde(default)]
+    pub api_base: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub organization_id: Option<String>,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub connect_timeout: Option<u64>,
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    #[serde(default)]
+    pub retry_base_delay_ms: Option<u64>,
+    /// Whether to request `stream: true` and print deltas as they arrive,
+    /// instead of waiting for the full response body.
+    #[serde(default)]
+    pub stream: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TextCompletionConfig {
+    #[serde(flatten)]
+    pub extra: ClientExtra,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatGptConfig {
+    #[serde(flatten)]
+    pub extra: ClientExtra,
+}
+
+/// Declares `(module, name, ConfigStruct, ClientStruct)` tuples and expands
+/// them into a tagged `ClientConfig` enum plus an `init()` that builds the
+/// client selected by the config file's `type` field.
+macro_rules! register_client {
+    ($(($module:ident, $name:literal, $config:ty, $client:ty)),+ $(,)?) => {
+        #[derive(Debug, Deserialize)]
+        #[serde(tag = "type")]
+        pub enum ClientConfig {
+            $(
+                #[serde(rename = $name)]
+                $module($config),
+            )+
+        }
+
+        impl ClientConfig {
+            /// Reads `path` (TOML) and instantiates the client it selects,
+            /// applying that client's `extra` overrides.
+            pub fn init(path: &str) -> Result<Box<dyn CodeCompletion>, Box<dyn std::error::Error>> {
+                let content = std::fs::read_to_string(path)?;
+                let config: ClientConfig = toml::from_str(&content)?;
+                Ok(match config {
+                    $(
+                        ClientConfig::$module(cfg) => {
+                            let mut client = <$client as CodeCompletion>::new();
+                            client.configure(&cfg.extra);
+                            Box::new(client)
+                        }
+                    )+
+                })
+            }
+
+            /// Like `init`, but falls back to the default `ChatGPT` backend
+            /// when `path` doesn't exist or fails to parse, so callers that
+            /// haven't set up a config file yet (a first `gpt_auto_test`
+            /// run, `gpt-macro-serve` with no provider configured) still
+            /// get a usable client.
+            pub fn init_or_default(path: &str) -> Box<dyn CodeCompletion> {
+                Self::init(path).unwrap_or_else(|_| Box::new(ChatGPT::new()))
+            }
+        }
+    };
+}
+
+register_client! {
+    (TextCompletion, "text-completion", TextCompletionConfig, TextCompletion),
+    (ChatGpt, "chatgpt", ChatGptConfig, ChatGPT),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_chatgpt_config() {
+        let config: ClientConfig = toml::from_str(
+            r#"
+            type = "chatgpt"
+            api_key = "sk-test"
+            max_retries = 5
+            "#,
+        )
+        .unwrap();
+        match config {
+            ClientConfig::ChatGpt(cfg) => {
+                assert_eq!(cfg.extra.api_key.as_deref(), Some("sk-test"));
+                assert_eq!(cfg.extra.max_retries, Some(5));
+            }
+            ClientConfig::TextCompletion(_) => panic!("expected ChatGpt variant"),
+        }
+    }
+
+    #[test]
+    fn parses_text_completion_config() {
+        let config: ClientConfig = toml::from_str(
+            r#"
+            type = "text-completion"
+            api_base = "https://example.com/v1/completions"
+            "#,
+        )
+        .unwrap();
+        match config {
+            ClientConfig::TextCompletion(cfg) => {
+                assert_eq!(
+                    cfg.extra.api_base.as_deref(),
+                    Some("https://example.com/v1/completions")
+                );
+            }
+            ClientConfig::ChatGpt(_) => panic!("expected TextCompletion variant"),
+        }
+    }
+
+    #[test]
+    fn rejects_missing_type_tag() {
+        let result: Result<ClientConfig, _> = toml::from_str(r#"api_key = "sk-test""#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_type_tag() {
+        let result: Result<ClientConfig, _> = toml::from_str(r#"type = "bogus""#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn init_falls_back_to_chatgpt_when_file_is_missing() {
+        let client = ClientConfig::init_or_default("/nonexistent/gpt-macro.toml");
+        // `ChatGPT` has no public way to distinguish itself from the
+        // outside, but `init_or_default` must not panic or propagate the
+        // missing-file error when no config has been set up yet.
+        drop(client);
+    }
+}
@@ -0,0 +1,8 @@
+// SPDX-License-Identifier: MIT
+
+pub mod chatgpt;
+pub mod completion;
+pub mod config;
+mod retry;
+pub mod server;
+pub mod text_completion;
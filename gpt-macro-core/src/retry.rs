@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: MIT
+
+// Retry/backoff and SSE line-framing shared by the `ChatGPT` and
+// `TextCompletion` backends, so a fix here doesn't need to be re-applied by
+// hand to each provider's copy.
+
+use std::ops::ControlFlow;
+
+use hyper::body::HttpBody;
+use hyper::Body;
+
+pub(crate) const DEFAULT_MAX_RETRIES: u32 = 3;
+pub(crate) const DEFAULT_RETRY_BASE_DELAY_MS: u64 = 500;
+
+/// Whether a non-2xx response should be retried: 429 and 5xx are
+/// transient, everything else is a persistent client error.
+pub(crate) fn should_retry(status: hyper::StatusCode, attempt: u32, max_retries: u32) -> bool {
+    attempt < max_retries && (status.as_u16() == 429 || status.is_server_error())
+}
+
+/// `Retry-After` if present, otherwise `base_delay_ms * 2^attempt`,
+/// saturating instead of overflowing for a large `attempt`.
+pub(crate) fn retry_delay(
+    response: &hyper::Response<Body>,
+    attempt: u32,
+    base_delay_ms: u64,
+) -> std::time::Duration {
+    response
+        .headers()
+        .get(hyper::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(std::time::Duration::from_secs)
+        .unwrap_or_else(|| {
+            let delay_ms = base_delay_ms.saturating_mul(2u64.saturating_pow(attempt));
+            std::time::Duration::from_millis(delay_ms)
+        })
+}
+
+/// Surfaces the API's `error.message` JSON field for a persistent 4xx,
+/// falling back to the raw body if it isn't in the expected shape.
+pub(crate) async fn api_error(
+    provider: &str,
+    response: hyper::Response<Body>,
+) -> Box<dyn std::error::Error> {
+    let status = response.status();
+    let body_str = match hyper::body::to_bytes(response.into_body()).await {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(err) => return err.into(),
+    };
+    let message = serde_json::from_str::<serde_json::Value>(&body_str)
+        .ok()
+        .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or(body_str);
+    format!("{} API error ({}): {}", provider, status, message).into()
+}
+
+/// Accumulates raw SSE bytes into complete lines and hands each `data: `
+/// frame's payload to `on_data`, stopping at a `[DONE]` frame or as soon as
+/// `on_data` returns `ControlFlow::Break`. Bytes are buffered across network
+/// chunks rather than decoded chunk-by-chunk, since a multi-byte UTF-8
+/// character can straddle a chunk boundary (ASCII `\n` never appears inside
+/// a UTF-8 continuation byte, so scanning the raw buffer for it is safe).
+pub(crate) async fn for_each_sse_data<F>(
+    mut body: Body,
+    mut on_data: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: FnMut(&str) -> ControlFlow<()>,
+{
+    let mut buf: Vec<u8> = Vec::new();
+    while let Some(chunk) = body.data().await {
+        buf.extend_from_slice(&chunk?);
+        while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+            let line = String::from_utf8_lossy(&buf[..pos]).trim().to_string();
+            buf.drain(..=pos);
+            let Some(data) = line.strip_prefix("data: ") else {
+                continue;
+            };
+            if data == "[DONE]" {
+                return Ok(());
+            }
+            if on_data(data).is_break() {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: hyper::StatusCode) -> hyper::Response<Body> {
+        hyper::Response::builder()
+            .status(status)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn should_retry_retries_429_and_5xx_within_budget() {
+        assert!(should_retry(hyper::StatusCode::TOO_MANY_REQUESTS, 0, 3));
+        assert!(should_retry(hyper::StatusCode::SERVICE_UNAVAILABLE, 2, 3));
+    }
+
+    #[test]
+    fn should_retry_stops_at_max_retries() {
+        assert!(!should_retry(hyper::StatusCode::TOO_MANY_REQUESTS, 3, 3));
+    }
+
+    #[test]
+    fn should_retry_ignores_persistent_client_errors() {
+        assert!(!should_retry(hyper::StatusCode::BAD_REQUEST, 0, 3));
+    }
+
+    #[test]
+    fn retry_delay_doubles_per_attempt() {
+        let resp = response(hyper::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            retry_delay(&resp, 0, 500),
+            std::time::Duration::from_millis(500)
+        );
+        assert_eq!(
+            retry_delay(&resp, 2, 500),
+            std::time::Duration::from_millis(2000)
+        );
+    }
+
+    #[test]
+    fn retry_delay_honors_retry_after_header() {
+        let resp = hyper::Response::builder()
+            .status(hyper::StatusCode::TOO_MANY_REQUESTS)
+            .header(hyper::header::RETRY_AFTER, "7")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            retry_delay(&resp, 0, 500),
+            std::time::Duration::from_secs(7)
+        );
+    }
+
+    #[test]
+    fn retry_delay_saturates_instead_of_overflowing() {
+        let resp = response(hyper::StatusCode::SERVICE_UNAVAILABLE);
+        // A large `attempt` (bounded only by the user-configured
+        // `max_retries`) must not panic on overflow.
+        assert_eq!(
+            retry_delay(&resp, u32::MAX, 500),
+            std::time::Duration::from_millis(u64::MAX)
+        );
+    }
+}
@@ -0,0 +1,219 @@
+// SPDX-License-Identifier: MIT
+
+// A local, OpenAI-compatible `POST /v1/chat/completions` server. Unlike the
+// `ChatGPT`/`TextCompletion` clients, it doesn't proxy to a provider: it
+// drives this crate's own test-generation pipeline and hands back the
+// generated tests in the same response envelope OpenAI clients expect, so
+// editors/CI (or `gpt_auto_test` itself, pointed at `127.0.0.1:8000`) can
+// call the generator as a local service.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::completion::CodeCompletion;
+use crate::config::{ClientConfig, DEFAULT_CONFIG_PATH};
+
+#[derive(Debug, Deserialize)]
+struct ServeMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServeRequest {
+    #[serde(default)]
+    model: Option<String>,
+    messages: Vec<ServeMessage>,
+    /// Extension field (not part of the OpenAI schema): the names of the
+    /// test functions to generate for the function body carried in the
+    /// last user message.
+    #[serde(default)]
+    test_names: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ServeResponseMessage {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ServeChoice {
+    index: u32,
+    message: ServeResponseMessage,
+    finish_reason: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct ServeResponse {
+    id: &'static str,
+    object: &'static str,
+    created: u64,
+    model: String,
+    choices: Vec<ServeChoice>,
+}
+
+fn bad_request(message: impl Into<String>) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .body(Body::from(message.into()))
+        .unwrap()
+}
+
+fn internal_error(message: impl Into<String>) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .body(Body::from(message.into()))
+        .unwrap()
+}
+
+/// Runs the crate's own `CodeCompletion` flow against the given function
+/// body and test names, off the async runtime, since `code_completion`
+/// blocks on its own `tokio::runtime::Runtime`. Goes through the
+/// `ClientConfig` registry rather than hardcoding `ChatGPT`, so pointing an
+/// editor at this local server can itself be backed by a configured
+/// provider (e.g. a local llama.cpp/ollama backend).
+fn generate_tests(
+    model: Option<String>,
+    function_body: String,
+    test_names: Vec<String>,
+) -> Result<String, String> {
+    let mut client = ClientConfig::init_or_default(DEFAULT_CONFIG_PATH);
+    if let Some(model) = model {
+        client.set_model(model);
+    }
+    client.init(
+        "You generate Rust unit tests for the given function. \
+         Respond only via the emit_tests tool."
+            .to_string(),
+    );
+    for test_name in &test_names {
+        client.add_context(format!("Generate a test function named `{}`.", test_name));
+    }
+    client.add_context(function_body);
+
+    client.code_completion().map_err(|err| err.to_string())
+}
+
+async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::POST || req.uri().path() != "/v1/chat/completions" {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    let body_bytes = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(bytes) => bytes,
+        Err(err) => return Ok(bad_request(err.to_string())),
+    };
+
+    let request: ServeRequest = match serde_json::from_slice(&body_bytes) {
+        Ok(request) => request,
+        Err(err) => return Ok(bad_request(err.to_string())),
+    };
+
+    let function_body = request
+        .messages
+        .iter()
+        .rev()
+        .find(|message| message.role == "user")
+        .map(|message| message.content.clone())
+        .unwrap_or_default();
+
+    let model = request.model.clone();
+    let (tx, rx) = oneshot::channel();
+    std::thread::spawn(move || {
+        let result = generate_tests(model, function_body, request.test_names);
+        let _ = tx.send(result);
+    });
+
+    let tests = match rx.await {
+        Ok(Ok(tests)) => tests,
+        Ok(Err(err)) => return Ok(internal_error(err)),
+        Err(_) => return Ok(internal_error("test generation task was dropped")),
+    };
+
+    let response = ServeResponse {
+        id: "gpt-macro-serve",
+        object: "chat.completion",
+        created: 0,
+        model: request.model.unwrap_or_else(|| "gpt-macro-local".to_string()),
+        choices: vec![ServeChoice {
+            index: 0,
+            message: ServeResponseMessage {
+                role: "assistant",
+                content: tests,
+            },
+            finish_reason: "stop",
+        }],
+    };
+
+    Ok(Response::new(Body::from(
+        serde_json::to_string(&response).unwrap(),
+    )))
+}
+
+/// Serves the local OpenAI-compatible endpoint at `addr` until `shutdown`
+/// resolves, then finishes in-flight requests before returning.
+pub async fn serve(addr: SocketAddr, shutdown: oneshot::Receiver<()>) -> Result<(), hyper::Error> {
+    let make_svc = make_service_fn(|_conn| async { Ok::<_, Infallible>(service_fn(handle)) });
+
+    Server::bind(&addr)
+        .serve(make_svc)
+        .with_graceful_shutdown(async {
+            shutdown.await.ok();
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(path: &str, body: &str) -> Request<Body> {
+        Request::builder()
+            .method(Method::POST)
+            .uri(path)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn handle_returns_404_for_non_post_request() {
+        let req = Request::builder()
+            .method(Method::GET)
+            .uri("/v1/chat/completions")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = handle(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn handle_returns_400_for_malformed_json() {
+        let response = handle(post("/v1/chat/completions", "not json")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn handle_returns_500_when_generation_fails() {
+        // `generate_tests` falls back to `ChatGPT`, whose `completion()`
+        // reads `OPENAI_API_KEY` and panics without it. Cleared explicitly
+        // so the test is deterministic regardless of the ambient shell.
+        std::env::remove_var("OPENAI_API_KEY");
+
+        let body = serde_json::json!({
+            "messages": [{"role": "user", "content": "fn add(a: i32, b: i32) -> i32 { a + b }"}],
+        })
+        .to_string();
+        let response = handle(post("/v1/chat/completions", &body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,353 @@
+// SPDX-License-Identifier: MIT
+// Akira Moroo <retrage01@gmail.com> 2023
+
+// Ask GPT-3.5 to complete the given function.
+// Use hyper to send a POST request to the GPT-3.5 API.
+
+use hyper::client::HttpConnector;
+use hyper::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use hyper::{Body, Client, Request, Uri};
+use hyper_proxy::{Intercept, Proxy, ProxyConnector};
+use hyper_tls::HttpsConnector;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::ops::ControlFlow;
+use tokio::runtime::Runtime;
+
+use crate::completion::CodeCompletion;
+use crate::config::ClientExtra;
+use crate::retry;
+
+#[derive(Deserialize, Serialize, Debug)]
+struct CompletionRequest {
+    model: String,
+    prompt: String,
+    max_tokens: u32,
+    temperature: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct CompletionResponse {
+    id: String,
+    object: String,
+    created: u64,
+    model: String,
+    choices: Vec<CompletionChoice>,
+    usage: CompletionUsage,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct CompletionChoice {
+    text: String,
+    index: u32,
+    logprobs: Option<u32>,
+    finish_reason: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct CompletionUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    total_tokens: u32,
+}
+
+/// One `data: {...}` frame of a `text/event-stream` response.
+#[derive(Debug, Deserialize)]
+struct CompletionChunk {
+    choices: Vec<CompletionChunkChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionChunkChoice {
+    text: String,
+}
+
+pub struct TextCompletion {
+    request: CompletionRequest,
+    response: Option<CompletionResponse>,
+    stream_content: Option<String>,
+    extra: Option<ClientExtra>,
+}
+
+impl TextCompletion {
+    const URL: &'static str = "https://api.openai.com/v1/completions";
+    const MODEL: &'static str = "text-davinci-003";
+
+    fn add_prompt(&mut self, content: String) {
+        self.request.prompt.push('\n');
+        self.request.prompt.push_str(&content);
+    }
+
+    /// Enables `stream: true` requests, printing deltas as they arrive
+    /// instead of waiting for the full response body.
+    pub fn set_stream(&mut self, stream: bool) {
+        self.request.stream = stream;
+    }
+
+    /// Reads a `text/event-stream` body frame by frame, printing each delta
+    /// as it arrives and accumulating them into the full completion text.
+    ///
+    /// A frame's `choices` array can be empty (e.g. Azure OpenAI's leading
+    /// content-filter annotation frame, or OpenAI's trailing usage frame
+    /// when `stream_options.include_usage` is set), so it's read with
+    /// `.first()` rather than indexed.
+    async fn consume_stream(body: Body) -> Result<String, Box<dyn std::error::Error>> {
+        let mut content = String::new();
+        let mut parse_err = None;
+        retry::for_each_sse_data(body, |data| {
+            let chunk: CompletionChunk = match serde_json::from_str(data) {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    parse_err = Some(err);
+                    return ControlFlow::Break(());
+                }
+            };
+            if let Some(delta) = chunk.choices.first().map(|choice| choice.text.clone()) {
+                print!("{}", delta);
+                use std::io::Write;
+                std::io::stdout().flush().ok();
+                content.push_str(&delta);
+            }
+            ControlFlow::Continue(())
+        })
+        .await?;
+        if let Some(err) = parse_err {
+            return Err(err.into());
+        }
+        Ok(content)
+    }
+
+    async fn completion(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let api_key = self
+            .extra
+            .as_ref()
+            .and_then(|e| e.api_key.clone())
+            .unwrap_or_else(|| {
+                std::env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY is not set")
+            });
+        let url = self
+            .extra
+            .as_ref()
+            .and_then(|e| e.api_base.clone())
+            .unwrap_or_else(|| Self::URL.to_string());
+        let uri: Uri = url.parse()?;
+
+        let https_connector = HttpsConnector::new();
+        let proxy_uri = self
+            .extra
+            .as_ref()
+            .and_then(|e| e.proxy.clone())
+            .or_else(|| std::env::var("HTTP_PROXY").ok());
+        let proxy_connector = if let Some(proxy_uri) = proxy_uri {
+            let proxy_uri = proxy_uri.parse().unwrap();
+            let proxy = Proxy::new(Intercept::All, proxy_uri);
+            let proxy_connector =
+                ProxyConnector::from_proxy(https_connector.clone(), proxy).unwrap();
+            Some(proxy_connector)
+        } else {
+            None
+        };
+        let client = proxy_connector.map_or_else(
+            || Box::new(Client::builder().build::<_, hyper::Body>(https_connector)) as Box<dyn Any>,
+            |proxy| Box::new(Client::builder().build::<_, hyper::Body>(proxy)),
+        );
+
+        let request = move |req: Request<Body>| {
+            if let Some(c) = client.downcast_ref::<Client<HttpsConnector<HttpConnector>>>() {
+                c.request(req)
+            } else if let Some(c) =
+                client.downcast_ref::<Client<ProxyConnector<HttpsConnector<HttpConnector>>>>()
+            {
+                c.request(req)
+            } else {
+                panic!("Unknown client type");
+            }
+        };
+        let connect_timeout = self.extra.as_ref().and_then(|e| e.connect_timeout);
+        let max_retries = self
+            .extra
+            .as_ref()
+            .and_then(|e| e.max_retries)
+            .unwrap_or(retry::DEFAULT_MAX_RETRIES);
+        let base_delay_ms = self
+            .extra
+            .as_ref()
+            .and_then(|e| e.retry_base_delay_ms)
+            .unwrap_or(retry::DEFAULT_RETRY_BASE_DELAY_MS);
+
+        let mut attempt: u32 = 0;
+        let response = loop {
+            let body = Body::from(serde_json::to_string(&self.request)?);
+            let mut request_body = Request::new(body);
+
+            *request_body.method_mut() = hyper::Method::POST;
+            *request_body.uri_mut() = uri.clone();
+
+            request_body
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+            request_body.headers_mut().insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {}", api_key)).unwrap(),
+            );
+            if let Some(organization_id) =
+                self.extra.as_ref().and_then(|e| e.organization_id.clone())
+            {
+                request_body.headers_mut().insert(
+                    "OpenAI-Organization",
+                    HeaderValue::from_str(&organization_id).unwrap(),
+                );
+            }
+
+            let response = match connect_timeout {
+                Some(secs) => {
+                    tokio::time::timeout(
+                        std::time::Duration::from_secs(secs),
+                        request(request_body),
+                    )
+                    .await??
+                }
+                None => request(request_body).await?,
+            };
+
+            if response.status().is_success() {
+                break response;
+            }
+            if !retry::should_retry(response.status(), attempt, max_retries) {
+                return Err(retry::api_error("TextCompletion", response).await);
+            }
+
+            tokio::time::sleep(retry::retry_delay(&response, attempt, base_delay_ms)).await;
+            attempt += 1;
+        };
+        if self.request.stream {
+            let content = Self::consume_stream(response.into_body()).await?;
+            println!();
+            self.stream_content = Some(content);
+            return Ok(());
+        }
+
+        let body_bytes = hyper::body::to_bytes(response.into_body()).await?;
+        let body_str = String::from_utf8(body_bytes.to_vec())?;
+
+        let response: CompletionResponse = serde_json::from_str(&body_str)?;
+
+        let content = response.choices[0].text.clone();
+        println!("Response from {}:\n{}", self.request.model, content);
+
+        self.response = Some(response);
+
+        Ok(())
+    }
+
+    fn extract_code(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let content = if let Some(content) = self.stream_content.clone() {
+            content
+        } else {
+            self.response.as_ref().ok_or("No response")?.choices[0]
+                .text
+                .clone()
+        };
+        // Remove the code block and remaining explanation text.
+        // Extract the test case in the code block. Other parts are removed.
+        let code_block = content
+            .split("```rust")
+            .nth(1)
+            .ok_or(format!("No code block start found: {}", content))?
+            .split("```")
+            .next()
+            .ok_or(format!("No code block end found: {}", content))?
+            .trim()
+            .to_string();
+
+        Ok(code_block)
+    }
+}
+
+impl CodeCompletion for TextCompletion {
+    fn new() -> Self {
+        Self {
+            request: CompletionRequest {
+                model: Self::MODEL.to_string(),
+                prompt: String::new(),
+                max_tokens: 1024,
+                temperature: 0.0,
+                stream: false,
+            },
+            response: None,
+            stream_content: None,
+            extra: None,
+        }
+    }
+
+    fn configure(&mut self, extra: &ClientExtra) {
+        if let Some(stream) = extra.stream {
+            self.set_stream(stream);
+        }
+        self.extra = Some(extra.clone());
+    }
+
+    fn set_model(&mut self, model: String) {
+        self.request.model = model;
+    }
+
+    fn set_temperature(&mut self, temperature: f32) {
+        self.request.temperature = temperature;
+    }
+
+    fn set_max_tokens(&mut self, max_tokens: u32) {
+        self.request.max_tokens = max_tokens;
+    }
+
+    fn init(&mut self, init_prompt: String) {
+        self.add_prompt(init_prompt);
+    }
+
+    fn add_context(&mut self, context: String) {
+        self.add_prompt(context)
+    }
+
+    fn code_completion(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+        let rt = Runtime::new()?;
+
+        rt.block_on(self.completion())?;
+
+        self.extract_code()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::body::Bytes;
+
+    #[tokio::test]
+    async fn consume_stream_skips_frames_with_empty_choices() {
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            // A leading content-filter annotation frame (as Azure OpenAI
+            // sends) and a trailing usage frame (as OpenAI sends with
+            // `stream_options.include_usage`) both carry an empty
+            // `choices` array.
+            sender
+                .send_data(Bytes::from("data: {\"choices\":[]}\n"))
+                .await
+                .ok();
+            sender
+                .send_data(Bytes::from("data: {\"choices\":[{\"text\":\"hi\"}]}\n"))
+                .await
+                .ok();
+            sender
+                .send_data(Bytes::from("data: {\"choices\":[]}\n"))
+                .await
+                .ok();
+            sender.send_data(Bytes::from("data: [DONE]\n")).await.ok();
+        });
+
+        let content = TextCompletion::consume_stream(body).await.unwrap();
+        assert_eq!(content, "hi");
+    }
+}
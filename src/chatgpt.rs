@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: MIT
+// Akira Moroo <retrage01@gmail.com> 2023
+
+// Expands `#[gpt_auto_test(...)]`: appends one generated `#[test]` per
+// requested name to the annotated function, each body supplied by the
+// provider selected through `gpt_macro_core`'s `ClientConfig` registry.
+
+use std::collections::HashSet;
+
+use gpt_macro_core::completion::CodeCompletion;
+use gpt_macro_core::config::{ClientConfig, DEFAULT_CONFIG_PATH};
+use proc_macro::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Ident, ItemFn};
+
+pub fn generate_tests(
+    input: TokenStream,
+    test_names: HashSet<Ident>,
+    model: Option<String>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+) -> syn::Result<TokenStream> {
+    let item_fn: ItemFn = syn::parse(input)?;
+    let fn_name = &item_fn.sig.ident;
+    let fn_source = item_fn.to_token_stream().to_string();
+
+    // Reads the config file picked by `ClientConfig` (see chunk0-1) so the
+    // same attribute works across providers, falling back to `ChatGPT` if
+    // none is configured yet.
+    let mut client = ClientConfig::init_or_default(DEFAULT_CONFIG_PATH);
+    if let Some(model) = model {
+        client.set_model(model);
+    }
+    if let Some(temperature) = temperature {
+        client.set_temperature(temperature);
+    }
+    if let Some(max_tokens) = max_tokens {
+        client.set_max_tokens(max_tokens);
+    }
+
+    client.init(
+        "You generate Rust unit tests for the given function. \
+         Respond only via the emit_tests tool."
+            .to_string(),
+    );
+
+    let mut tests = proc_macro2::TokenStream::new();
+    for test_name in &test_names {
+        client.add_context(format!(
+            "Function under test:\n{}\n\nGenerate a test function named `{}`.",
+            fn_source, test_name
+        ));
+        let generated = client
+            .code_completion()
+            .map_err(|err| syn::Error::new_spanned(fn_name, err.to_string()))?;
+        let generated: proc_macro2::TokenStream = generated
+            .parse()
+            .map_err(|_| syn::Error::new_spanned(fn_name, "generated tests were not valid Rust"))?;
+        tests.extend(generated);
+    }
+
+    Ok(quote! {
+        #item_fn
+
+        #tests
+    }
+    .into())
+}
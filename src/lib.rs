@@ -5,29 +5,110 @@ use proc_macro::TokenStream;
 use std::collections::HashSet;
 use syn::{
     parse::{Parse, ParseStream, Result},
-    parse_macro_input, Ident, Token,
+    parse_macro_input, Error, Ident, Lit, Token,
 };
 
+// Code generation for the attribute lives here rather than in a `pub`
+// module: this crate has `proc-macro = true`, so it can only export
+// `#[proc_macro_attribute]`-style items. The completion backends it drives
+// (`ChatGPT`, `TextCompletion`, the provider registry, the local serve
+// mode) live in the separate `gpt-macro-core` crate instead, which this
+// module depends on like any other regular library.
 mod chatgpt;
 
-/// Parses a list of test function names separated by commas.
+/// A single item inside the attribute's argument list: either a bare test
+/// function name or a `key = value` generation parameter.
+enum AttrItem {
+    TestName(Ident),
+    Param(Ident, Lit),
+}
+
+impl Parse for AttrItem {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident: Ident = input.parse()?;
+        if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            let lit: Lit = input.parse()?;
+            Ok(AttrItem::Param(ident, lit))
+        } else {
+            Ok(AttrItem::TestName(ident))
+        }
+    }
+}
+
+/// Parses a comma-separated list of test function names, optionally
+/// followed by `key = value` generation parameters.
 ///
-/// test_valid, test_div_by_zero
+/// test_valid, test_div_by_zero, model = "gpt-4o", temperature = 0.2, max_tokens = 2048
 ///
-/// The function name is used to generate the test function name.
+/// The function names are used to generate the test function names; the
+/// parameters are threaded through to the active `CodeCompletion` backend.
 struct Args {
     test_names: HashSet<Ident>,
+    model: Option<String>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
 }
 
 impl Parse for Args {
     fn parse(input: ParseStream) -> Result<Self> {
-        let test_names = input.parse_terminated::<Ident, Token![,]>(Ident::parse)?;
+        let items = input.parse_terminated::<AttrItem, Token![,]>(AttrItem::parse)?;
+
+        let mut test_names = HashSet::new();
+        let mut model = None;
+        let mut temperature = None;
+        let mut max_tokens = None;
+
+        for item in items {
+            match item {
+                AttrItem::TestName(ident) => {
+                    test_names.insert(ident);
+                }
+                AttrItem::Param(ident, lit) => match ident.to_string().as_str() {
+                    "model" => model = Some(expect_str(&lit)?),
+                    "temperature" => temperature = Some(expect_float(&lit)?),
+                    "max_tokens" => max_tokens = Some(expect_int(&lit)?),
+                    other => {
+                        return Err(Error::new(
+                            ident.span(),
+                            format!("unknown gpt_auto_test parameter `{}`", other),
+                        ))
+                    }
+                },
+            }
+        }
+
         Ok(Args {
-            test_names: test_names.into_iter().collect(),
+            test_names,
+            model,
+            temperature,
+            max_tokens,
         })
     }
 }
 
+fn expect_str(lit: &Lit) -> Result<String> {
+    match lit {
+        Lit::Str(s) => Ok(s.value()),
+        _ => Err(Error::new_spanned(lit, "expected a string literal")),
+    }
+}
+
+fn expect_float(lit: &Lit) -> Result<f32> {
+    match lit {
+        Lit::Float(f) => f.base10_parse(),
+        Lit::Int(i) => i.base10_parse(),
+        _ => Err(Error::new_spanned(lit, "expected a numeric literal")),
+    }
+}
+
+fn expect_int(lit: &Lit) -> Result<u32> {
+    match lit {
+        Lit::Int(i) => i.base10_parse(),
+        _ => Err(Error::new_spanned(lit, "expected an integer literal")),
+    }
+}
+
 /// Attribute macro for automatically generating tests for functions.
 ///
 /// # Example
@@ -35,17 +116,101 @@ impl Parse for Args {
 /// ```
 /// use r#gpt_auto_test::gpt_auto_test;
 ///
-/// #[gpt_auto_test(test_valid, test_div_by_zero)]
+/// #[gpt_auto_test(test_valid, test_div_by_zero, model = "gpt-4o", temperature = 0.2, max_tokens = 2048)]
 /// fn div_u32(a: u32, b: u32) -> u32 {
 ///    a / b
 /// }
 /// ```
 #[proc_macro_attribute]
 pub fn gpt_auto_test(args: TokenStream, input: TokenStream) -> TokenStream {
-    // Parse the list of test function names that should be generated.
+    // Parse the list of test function names, plus any generation overrides,
+    // that should be applied.
     let args = parse_macro_input!(args as Args);
 
-    let output = chatgpt::generate_tests(input, args.test_names).unwrap();
+    match chatgpt::generate_tests(
+        input,
+        args.test_names,
+        args.model,
+        args.temperature,
+        args.max_tokens,
+    ) {
+        Ok(output) => output,
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    output
+    fn parse_args(input: &str) -> Args {
+        syn::parse_str::<Args>(input).unwrap()
+    }
+
+    #[test]
+    fn parses_bare_test_names() {
+        let args = parse_args("test_valid, test_div_by_zero");
+        assert_eq!(args.test_names.len(), 2);
+        assert!(args.test_names.iter().any(|id| id == "test_valid"));
+        assert!(args.test_names.iter().any(|id| id == "test_div_by_zero"));
+        assert_eq!(args.model, None);
+        assert_eq!(args.temperature, None);
+        assert_eq!(args.max_tokens, None);
+    }
+
+    #[test]
+    fn parses_params_alongside_test_names() {
+        let args = parse_args(
+            r#"test_valid, model = "gpt-4o", temperature = 0.2, max_tokens = 2048"#,
+        );
+        assert_eq!(args.test_names.len(), 1);
+        assert_eq!(args.model, Some("gpt-4o".to_string()));
+        assert_eq!(args.temperature, Some(0.2));
+        assert_eq!(args.max_tokens, Some(2048));
+    }
+
+    #[test]
+    fn rejects_unknown_param() {
+        let err = syn::parse_str::<Args>(r#"unknown_param = 1"#).unwrap_err();
+        assert!(err.to_string().contains("unknown gpt_auto_test parameter"));
+    }
+
+    #[test]
+    fn expect_str_accepts_string_literal() {
+        let lit: Lit = syn::parse_str(r#""gpt-4o""#).unwrap();
+        assert_eq!(expect_str(&lit).unwrap(), "gpt-4o");
+    }
+
+    #[test]
+    fn expect_str_rejects_non_string() {
+        let lit: Lit = syn::parse_str("1").unwrap();
+        assert!(expect_str(&lit).is_err());
+    }
+
+    #[test]
+    fn expect_float_accepts_float_and_int_literals() {
+        let float_lit: Lit = syn::parse_str("0.2").unwrap();
+        assert_eq!(expect_float(&float_lit).unwrap(), 0.2);
+
+        let int_lit: Lit = syn::parse_str("2").unwrap();
+        assert_eq!(expect_float(&int_lit).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn expect_float_rejects_non_numeric() {
+        let lit: Lit = syn::parse_str(r#""nope""#).unwrap();
+        assert!(expect_float(&lit).is_err());
+    }
+
+    #[test]
+    fn expect_int_accepts_int_literal() {
+        let lit: Lit = syn::parse_str("2048").unwrap();
+        assert_eq!(expect_int(&lit).unwrap(), 2048);
+    }
+
+    #[test]
+    fn expect_int_rejects_non_int() {
+        let lit: Lit = syn::parse_str("2.0").unwrap();
+        assert!(expect_int(&lit).is_err());
+    }
 }